@@ -0,0 +1,36 @@
+//! Object capabilities: bit flags which describe what operations an object
+//! stored on the `YubiHSM2` may be used to perform.
+//!
+//! <https://developers.yubico.com/YubiHSM2/Concepts/Capability.html>
+
+bitflags! {
+    /// Object capabilities
+    pub struct Capability: u64 {
+        /// `generate-asymmetric-key`: generate asymmetric keys
+        const GENERATE_ASYMMETRIC_KEY = 0x0000_0000_0000_0001;
+
+        /// `sign-pkcs`: sign data using RSASSA-PKCS#1v1.5
+        const SIGN_PKCS = 0x0000_0000_0000_0002;
+
+        /// `sign-pss`: sign data using RSASSA-PSS
+        const SIGN_PSS = 0x0000_0000_0000_0004;
+
+        /// `sign-ecdsa`: sign data using ECDSA
+        const SIGN_ECDSA = 0x0000_0000_0000_0008;
+
+        /// `sign-eddsa`: sign data using EdDSA
+        const SIGN_EDDSA = 0x0000_0000_0000_0010;
+
+        /// `decrypt-pkcs`: decrypt data encrypted using RSAES-PKCS#1v1.5
+        const DECRYPT_PKCS = 0x0000_0000_0000_0020;
+
+        /// `decrypt-oaep`: decrypt data encrypted using RSA-OAEP
+        const DECRYPT_OAEP = 0x0000_0000_0000_0040;
+
+        /// `export-wrapped`: export other objects under wrap
+        const EXPORT_WRAPPED = 0x0000_0000_0000_0080;
+
+        /// `import-wrapped`: import objects under wrap
+        const IMPORT_WRAPPED = 0x0000_0000_0000_0100;
+    }
+}
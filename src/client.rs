@@ -0,0 +1,143 @@
+//! High-level client which owns a `Connector` and lends out encrypted
+//! `Session`s on demand.
+//!
+//! Unlike `Session`, which owns its connector and closes when dropped, a
+//! `Client` holds onto a single connector and set of credentials for as
+//! long as the application needs them, re-establishing sessions (via the
+//! `Session` reconnect machinery) as needed rather than requiring callers
+//! to manage `Session` lifetimes by hand.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use connector::Connector;
+use securechannel::StaticKeys;
+use session::{Session, SessionError};
+use ObjectId;
+
+/// Credentials used to open and re-open encrypted `Session`s with the HSM
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    /// ID of the auth key to authenticate with
+    pub auth_key_id: ObjectId,
+
+    /// Static keys derived from (or equal to) the auth key's keypair
+    pub static_keys: StaticKeys,
+}
+
+impl Credentials {
+    /// Create new credentials from an auth key ID and static keys
+    pub fn new(auth_key_id: ObjectId, static_keys: StaticKeys) -> Self {
+        Self {
+            auth_key_id,
+            static_keys,
+        }
+    }
+
+    /// Create new credentials, deriving static keys from a password
+    #[cfg(feature = "passwords")]
+    pub fn from_password(auth_key_id: ObjectId, password: &str) -> Self {
+        Self::new(
+            auth_key_id,
+            StaticKeys::derive_from_password(
+                password.as_bytes(),
+                ::session::PBKDF2_SALT,
+                ::session::PBKDF2_ITERATIONS,
+            ),
+        )
+    }
+}
+
+/// A `Client` owns a `Connector` and a set of `Credentials`, and lends out
+/// encrypted `Session`s on demand, opening a new one the first time it's
+/// needed and reusing it thereafter (with automatic reconnection when the
+/// `Client` was created with `reconnect: true`).
+pub struct Client<C: Connector> {
+    connector: Arc<C>,
+    credentials: Credentials,
+    reconnect: bool,
+    session: Mutex<Option<Session<Arc<C>>>>,
+}
+
+impl<C: Connector> Client<C> {
+    /// Create a `Client` and immediately open a session with it
+    pub fn open(
+        connector: C,
+        credentials: Credentials,
+        reconnect: bool,
+    ) -> Result<Self, SessionError> {
+        let client = Self::create(connector, credentials, reconnect);
+        client.connect()?;
+        Ok(client)
+    }
+
+    /// Create a `Client`, deferring opening a session until one is needed
+    pub fn create(connector: C, credentials: Credentials, reconnect: bool) -> Self {
+        Self {
+            connector: Arc::new(connector),
+            credentials,
+            reconnect,
+            session: Mutex::new(None),
+        }
+    }
+
+    /// Ensure a session is open, establishing one if necessary. A no-op if
+    /// a session is already open.
+    pub fn connect(&self) -> Result<(), SessionError> {
+        self.session().map(|_| ())
+    }
+
+    /// Measure the round-trip time of a request to the `yubihsm-connector`
+    /// (or USB device) this client talks to, opening a session first if one
+    /// isn't already open.
+    ///
+    /// This only exercises the connector's status endpoint, not the
+    /// authenticated encrypted channel, so it tells you the connector/device
+    /// is reachable — not that the currently open `Session` is still usable.
+    /// A stale or dropped encrypted session won't be detected (or healed via
+    /// reconnection) by `ping()`; that only happens on the next call that
+    /// actually sends an encrypted command.
+    pub fn ping(&self) -> Result<Duration, SessionError> {
+        let mut session = self.session()?;
+        let started_at = Instant::now();
+        session.connector_status()?;
+        Ok(started_at.elapsed())
+    }
+
+    /// Borrow the currently open session, opening a new one if none is open
+    pub fn session(&self) -> Result<SessionGuard<C>, SessionError> {
+        let mut guard = self.session.lock().unwrap();
+
+        if guard.is_none() {
+            *guard = Some(Session::new(
+                Arc::clone(&self.connector),
+                self.credentials.auth_key_id,
+                self.credentials.static_keys.clone(),
+                self.reconnect,
+            )?);
+        }
+
+        Ok(SessionGuard { guard })
+    }
+}
+
+/// Guard which dereferences to the `Session` currently held open by a
+/// `Client`
+pub struct SessionGuard<'c, C: Connector + 'c> {
+    guard: MutexGuard<'c, Option<Session<Arc<C>>>>,
+}
+
+impl<'c, C: Connector> Deref for SessionGuard<'c, C> {
+    type Target = Session<Arc<C>>;
+
+    fn deref(&self) -> &Session<Arc<C>> {
+        self.guard.as_ref().expect("session should be open")
+    }
+}
+
+impl<'c, C: Connector> DerefMut for SessionGuard<'c, C> {
+    fn deref_mut(&mut self) -> &mut Session<Arc<C>> {
+        self.guard.as_mut().expect("session should be open")
+    }
+}
@@ -0,0 +1,297 @@
+//! Get entries from the device's tamper-evident audit log, and verify the
+//! hash chain linking them together.
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Get_Log_Entries.html>
+//! <https://developers.yubico.com/YubiHSM2/Concepts/Logs.html>
+
+use byteorder::{BigEndian, ByteOrder};
+use sha2::{Digest, Sha256};
+
+use super::{Command, Response};
+use session::{Session, SessionError, SessionErrorKind::ProtocolError};
+use {CommandType, Connector, ObjectId};
+
+/// Number of bytes in a log entry's digest
+pub const LOG_DIGEST_SIZE: usize = 16;
+
+/// Number of bytes in a single serialized log entry, digest included
+const LOG_ENTRY_SIZE: usize = 32;
+
+/// Get the boot/auth events which have not yet been logged (because the log
+/// store was full) plus the entries currently in the log store
+pub fn get_log_entries<C: Connector>(session: &mut Session<C>) -> Result<LogEntries, SessionError> {
+    let response = session.send_encrypted_command(GetLogEntriesCommand {})?;
+    LogEntries::parse(&response.0)
+}
+
+/// A single entry in the device's audit log
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LogEntry {
+    /// Monotonically increasing index of this entry within the log store
+    pub item: u16,
+
+    /// Type of command which was logged
+    pub command_type: CommandType,
+
+    /// Length of the command's input
+    pub length: u16,
+
+    /// Object ID of the session key which authenticated the command
+    pub session_key_id: ObjectId,
+
+    /// Object ID of the command's (primary) target key
+    pub target_key_id: ObjectId,
+
+    /// Object ID of the command's secondary target key, if any
+    pub second_key_id: ObjectId,
+
+    /// Result code returned for the command
+    pub result: u8,
+
+    /// Device's internal tick counter when the command was logged
+    pub systick: u32,
+
+    /// Truncated (first 16 bytes of) SHA-256 digest chaining this entry to
+    /// the one logged before it
+    pub digest: [u8; LOG_DIGEST_SIZE],
+}
+
+impl LogEntry {
+    /// Parse a single `LOG_ENTRY_SIZE`-byte log entry
+    fn parse(bytes: &[u8]) -> Result<Self, SessionError> {
+        ensure!(
+            bytes.len() == LOG_ENTRY_SIZE,
+            ProtocolError,
+            "expected a {}-byte log entry, got {}",
+            LOG_ENTRY_SIZE,
+            bytes.len()
+        );
+
+        let mut digest = [0u8; LOG_DIGEST_SIZE];
+        digest.copy_from_slice(&bytes[16..32]);
+
+        Ok(Self {
+            item: BigEndian::read_u16(&bytes[0..2]),
+            command_type: CommandType::from_u8(bytes[2]).map_err(|e| err!(ProtocolError, e))?,
+            length: BigEndian::read_u16(&bytes[3..5]),
+            session_key_id: BigEndian::read_u16(&bytes[5..7]),
+            target_key_id: BigEndian::read_u16(&bytes[7..9]),
+            second_key_id: BigEndian::read_u16(&bytes[9..11]),
+            result: bytes[11],
+            systick: BigEndian::read_u32(&bytes[12..16]),
+            digest,
+        })
+    }
+
+    /// Serialize this entry's fields (excluding its own digest) in the same
+    /// order the device uses when computing the hash chain
+    fn digest_input(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.item.to_be_bytes());
+        bytes.push(self.command_type.to_u8());
+        bytes.extend_from_slice(&self.length.to_be_bytes());
+        bytes.extend_from_slice(&self.session_key_id.to_be_bytes());
+        bytes.extend_from_slice(&self.target_key_id.to_be_bytes());
+        bytes.extend_from_slice(&self.second_key_id.to_be_bytes());
+        bytes.push(self.result);
+        bytes.extend_from_slice(&self.systick.to_be_bytes());
+        bytes
+    }
+}
+
+/// Parsed response from `commands::get_log_entries`
+#[derive(Clone, Debug)]
+pub struct LogEntries {
+    /// Number of boot events which occurred but went unrecorded because the
+    /// log store was full
+    pub unlogged_boot_events: u16,
+
+    /// Number of authentication events which occurred but went unrecorded
+    /// because the log store was full
+    pub unlogged_auth_events: u16,
+
+    /// Log entries currently held in the log store, in ascending order
+    pub entries: Vec<LogEntry>,
+}
+
+impl LogEntries {
+    /// Parse the raw response body of `commands::get_log_entries`
+    fn parse(bytes: &[u8]) -> Result<Self, SessionError> {
+        ensure!(
+            bytes.len() >= 5,
+            ProtocolError,
+            "log response too short: {} bytes",
+            bytes.len()
+        );
+
+        let unlogged_boot_events = BigEndian::read_u16(&bytes[0..2]);
+        let unlogged_auth_events = BigEndian::read_u16(&bytes[2..4]);
+        let num_entries = bytes[4] as usize;
+        let entries_bytes = &bytes[5..];
+
+        ensure!(
+            entries_bytes.len() == num_entries * LOG_ENTRY_SIZE,
+            ProtocolError,
+            "expected {} log entries ({} bytes), got {} bytes",
+            num_entries,
+            num_entries * LOG_ENTRY_SIZE,
+            entries_bytes.len()
+        );
+
+        let entries = entries_bytes
+            .chunks(LOG_ENTRY_SIZE)
+            .map(LogEntry::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            unlogged_boot_events,
+            unlogged_auth_events,
+            entries,
+        })
+    }
+
+    /// Walk the hash chain linking these entries together, confirming each
+    /// entry's digest is the truncated SHA-256 of its own fields
+    /// concatenated with the previous entry's digest.
+    ///
+    /// `anchor_digest` should be the digest of the entry immediately
+    /// preceding the first entry here (e.g. saved from a previous
+    /// `get_log_entries` call). If `None`, the first entry in `self.entries`
+    /// is trusted as the start of the chain and only entries after it are
+    /// verified.
+    ///
+    /// Returns an error identifying the first entry (by its `item` index)
+    /// whose digest doesn't match.
+    pub fn verify(&self, anchor_digest: Option<[u8; LOG_DIGEST_SIZE]>) -> Result<(), SessionError> {
+        let (mut previous_digest, remaining) = match anchor_digest {
+            Some(digest) => (digest, &self.entries[..]),
+            None => match self.entries.split_first() {
+                Some((first, rest)) => (first.digest, rest),
+                None => return Ok(()),
+            },
+        };
+
+        for entry in remaining {
+            let mut preimage = entry.digest_input();
+            preimage.extend_from_slice(&previous_digest);
+
+            let computed = Sha256::digest(&preimage);
+
+            ensure!(
+                computed[..LOG_DIGEST_SIZE] == entry.digest[..],
+                ProtocolError,
+                "log hash chain broken at entry {}",
+                entry.item
+            );
+
+            previous_digest = entry.digest;
+        }
+
+        Ok(())
+    }
+}
+
+/// Request parameters for `commands::get_log_entries`
+#[derive(Serialize, Debug)]
+pub(crate) struct GetLogEntriesCommand {}
+
+impl Command for GetLogEntriesCommand {
+    type ResponseType = GetLogEntriesResponse;
+}
+
+/// Raw response from `commands::get_log_entries`
+#[derive(Deserialize, Debug)]
+pub(crate) struct GetLogEntriesResponse(pub(crate) Vec<u8>);
+
+impl Response for GetLogEntriesResponse {
+    const COMMAND_TYPE: CommandType = CommandType::GetLogEntries;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a `LogEntry` with the given `item`/`systick`, leaving the rest
+    /// of its fields fixed, and compute its digest by chaining it onto
+    /// `previous_digest` the same way the device does.
+    fn chained_entry(item: u16, systick: u32, previous_digest: [u8; LOG_DIGEST_SIZE]) -> LogEntry {
+        let mut entry = LogEntry {
+            item,
+            command_type: CommandType::GetLogEntries,
+            length: 0,
+            session_key_id: 0,
+            target_key_id: 0,
+            second_key_id: 0,
+            result: 0,
+            systick,
+            digest: [0u8; LOG_DIGEST_SIZE],
+        };
+
+        let mut preimage = entry.digest_input();
+        preimage.extend_from_slice(&previous_digest);
+        let computed = Sha256::digest(&preimage);
+        entry.digest.copy_from_slice(&computed[..LOG_DIGEST_SIZE]);
+        entry
+    }
+
+    #[test]
+    fn verify_accepts_an_intact_chain() {
+        let anchor = [0u8; LOG_DIGEST_SIZE];
+        let first = chained_entry(1, 100, anchor);
+        let second = chained_entry(2, 101, first.digest);
+        let third = chained_entry(3, 102, second.digest);
+
+        let log = LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            entries: vec![first, second, third],
+        };
+
+        assert!(log.verify(Some(anchor)).is_ok());
+    }
+
+    #[test]
+    fn verify_trusts_the_first_entry_without_an_anchor() {
+        let first = chained_entry(1, 100, [0xff; LOG_DIGEST_SIZE]);
+        let second = chained_entry(2, 101, first.digest);
+
+        let log = LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            entries: vec![first, second],
+        };
+
+        assert!(log.verify(None).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_a_chain_with_a_tampered_entry() {
+        let anchor = [0u8; LOG_DIGEST_SIZE];
+        let first = chained_entry(1, 100, anchor);
+        let mut second = chained_entry(2, 101, first.digest);
+        let third = chained_entry(3, 102, second.digest);
+
+        // Tamper with the second entry after its digest was computed, so
+        // the chain still parses but no longer verifies starting there.
+        second.systick = 999;
+
+        // The first entry on its own is untouched and still verifies...
+        let first_only = LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            entries: vec![first.clone()],
+        };
+        assert!(first_only.verify(Some(anchor)).is_ok());
+
+        // ...but the chain breaks as soon as the tampered second entry is
+        // included.
+        let log = LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            entries: vec![first, second, third],
+        };
+
+        let error = log.verify(Some(anchor)).unwrap_err();
+        assert_eq!(error.kind(), ProtocolError);
+    }
+}
@@ -0,0 +1,38 @@
+//! Tell the device that log entries up to (and including) a given index
+//! have been consumed, freeing that space in its log store.
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Set_Log_Index.html>
+
+use super::{Command, Response};
+use session::{Session, SessionError};
+use {CommandType, Connector};
+
+/// Acknowledge log entries up to and including `index` as consumed, so the
+/// device can free the corresponding space in its log store.
+///
+/// Pairs naturally with the forced-audit option
+/// (`commands::get_force_audit_option`): once the store is full the device
+/// refuses further operations until entries are freed this way.
+pub fn set_log_index<C: Connector>(session: &mut Session<C>, index: u16) -> Result<(), SessionError> {
+    session.send_encrypted_command(SetLogIndexCommand { index })?;
+    Ok(())
+}
+
+/// Request parameters for `commands::set_log_index`
+#[derive(Serialize, Debug)]
+pub(crate) struct SetLogIndexCommand {
+    /// Index of the last log entry which has been read
+    pub index: u16,
+}
+
+impl Command for SetLogIndexCommand {
+    type ResponseType = SetLogIndexResponse;
+}
+
+/// Response from `commands::set_log_index` (empty on success)
+#[derive(Deserialize, Debug)]
+pub(crate) struct SetLogIndexResponse;
+
+impl Response for SetLogIndexResponse {
+    const COMMAND_TYPE: CommandType = CommandType::SetLogIndex;
+}
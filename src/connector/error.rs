@@ -0,0 +1,80 @@
+use failure::{Context, Fail};
+use std::fmt::{self, Display};
+
+/// Connector errors
+#[derive(Debug)]
+pub struct ConnectorError {
+    inner: Context<ConnectorErrorKind>,
+}
+
+/// Kinds of connector errors
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Fail)]
+pub enum ConnectorErrorKind {
+    /// Connector couldn't be created/opened
+    #[fail(display = "couldn't open connector")]
+    ConnectionFailed,
+
+    /// Error making a request to the HSM
+    #[fail(display = "request error")]
+    RequestError,
+
+    /// Error parsing a response from the HSM
+    #[fail(display = "response error")]
+    ResponseError,
+
+    /// The requested USB device could not be found
+    #[fail(display = "USB device not found")]
+    DeviceNotFound,
+}
+
+impl ConnectorError {
+    /// Get the kind of error that occurred
+    pub fn kind(&self) -> ConnectorErrorKind {
+        *self.inner.get_context()
+    }
+}
+
+impl Display for ConnectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl Fail for ConnectorError {
+    fn cause(&self) -> Option<&Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&::failure::Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl From<ConnectorErrorKind> for ConnectorError {
+    fn from(kind: ConnectorErrorKind) -> Self {
+        Context::new(kind).into()
+    }
+}
+
+impl From<Context<ConnectorErrorKind>> for ConnectorError {
+    fn from(inner: Context<ConnectorErrorKind>) -> Self {
+        ConnectorError { inner }
+    }
+}
+
+/// Create a new `ConnectorError` with the given kind and a formatted
+/// message, returning it as an `Err` from the current function
+macro_rules! connector_fail {
+    ($kind:ident, $msg:expr) => {
+        return Err(::failure::Fail::context(
+            ::failure::Context::new($msg.to_string()),
+            ::connector::error::ConnectorErrorKind::$kind,
+        ).into());
+    };
+    ($kind:ident, $fmt:expr, $($arg:tt)+) => {
+        return Err(::failure::Fail::context(
+            ::failure::Context::new(format!($fmt, $($arg)+)),
+            ::connector::error::ConnectorErrorKind::$kind,
+        ).into());
+    };
+}
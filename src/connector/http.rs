@@ -0,0 +1,112 @@
+//! Connector which communicates with a `yubihsm-connector` process over HTTP
+//!
+//! <https://developers.yubico.com/YubiHSM2/Component_Reference/yubihsm-connector/>
+
+use failure::ResultExt;
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{Connector, ConnectorError, ConnectorErrorKind::*, Status};
+
+/// Default host to connect to
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Default port the connector listens on
+pub const DEFAULT_PORT: u16 = 12345;
+
+/// Default connection/request timeout
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Configuration for connecting to a `yubihsm-connector` process over HTTP
+#[derive(Clone, Debug)]
+pub struct HttpConfig {
+    /// Host the connector is listening on
+    pub host: String,
+
+    /// Port the connector is listening on
+    pub port: u16,
+
+    /// Timeout for connector requests
+    pub timeout: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_owned(),
+            port: DEFAULT_PORT,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+impl fmt::Display for HttpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "http://{}:{}", self.host, self.port)
+    }
+}
+
+/// Connector which communicates with a `yubihsm-connector` process over HTTP
+pub struct HttpConnector {
+    config: HttpConfig,
+}
+
+impl HttpConnector {
+    /// Open a connection to a `yubihsm-connector` process
+    pub fn open(config: HttpConfig) -> Result<Self, ConnectorError> {
+        let connector = Self { config };
+        connector.status()?;
+        Ok(connector)
+    }
+
+    /// Make an HTTP POST request to the connector at the given path
+    fn post(&self, path: &str, body: &[u8]) -> Result<Vec<u8>, ConnectorError> {
+        let url = format!("{}/{}", self.config, path);
+
+        let mut response = ::reqwest::Client::builder()
+            .timeout(self.config.timeout)
+            .build()
+            .context(ConnectionFailed)?
+            .post(&url)
+            .body(body.to_vec())
+            .send()
+            .context(RequestError)?;
+
+        if !response.status().is_success() {
+            connector_fail!(
+                ResponseError,
+                "unexpected HTTP status from {}: {}",
+                url,
+                response.status()
+            );
+        }
+
+        let mut body = Vec::new();
+        response.read_to_end(&mut body).context(ResponseError)?;
+
+        Ok(body)
+    }
+}
+
+impl Connector for HttpConnector {
+    fn status(&self) -> Result<Status, ConnectorError> {
+        let response = self.post("connector/status", b"")?;
+        let message = String::from_utf8_lossy(&response)
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_owned();
+
+        Ok(Status {
+            message,
+            serial_number: None,
+        })
+    }
+
+    fn send_command(&self, _uuid: Uuid, command: Vec<u8>) -> Result<Vec<u8>, ConnectorError> {
+        self.post("connector/api", &command)
+    }
+}
@@ -0,0 +1,47 @@
+//! Connectors for sending commands to a `YubiHSM2`, either over HTTP via
+//! the `yubihsm-connector` service, or directly over USB.
+
+#[macro_use]
+mod error;
+mod http;
+mod usb;
+
+pub use self::error::{ConnectorError, ConnectorErrorKind};
+pub use self::http::{HttpConfig, HttpConnector};
+pub use self::usb::{UsbConfig, UsbConnector};
+
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Connectors which allow sending commands to a `YubiHSM2`
+pub trait Connector: Send + Sync {
+    /// Fetch the current status of the connector (and, transitively, of the
+    /// device it is attached to)
+    fn status(&self) -> Result<Status, ConnectorError>;
+
+    /// Send a command to the HSM, tagged with a UUID for logging/tracing,
+    /// and return the raw response bytes
+    fn send_command(&self, uuid: Uuid, command: Vec<u8>) -> Result<Vec<u8>, ConnectorError>;
+}
+
+/// Any `Arc`-wrapped connector is itself a connector, so a single connector
+/// instance can be shared between a `Client` and the `Session`s it lends out
+impl<C: Connector> Connector for Arc<C> {
+    fn status(&self) -> Result<Status, ConnectorError> {
+        (**self).status()
+    }
+
+    fn send_command(&self, uuid: Uuid, command: Vec<u8>) -> Result<Vec<u8>, ConnectorError> {
+        (**self).send_command(uuid, command)
+    }
+}
+
+/// Status message returned from a healthy connector
+#[derive(Debug)]
+pub struct Status {
+    /// Status message, expected to be "OK" when the connector is healthy
+    pub message: String,
+
+    /// Serial number of the device the connector is attached to, if known
+    pub serial_number: Option<String>,
+}
@@ -0,0 +1,186 @@
+//! Connector which talks directly to a `YubiHSM2` over USB, without going
+//! through a `yubihsm-connector` process.
+//!
+//! <https://developers.yubico.com/YubiHSM2/Component_Reference/>
+
+use failure::ResultExt;
+use libusb::{Context, Direction, TransferType};
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::{Connector, ConnectorError, ConnectorErrorKind::*, Status};
+
+/// USB vendor ID for Yubico
+pub const YUBICO_VENDOR_ID: u16 = 0x1050;
+
+/// USB product ID for the `YubiHSM2`
+pub const YUBIHSM2_PRODUCT_ID: u16 = 0x0030;
+
+/// Default timeout for USB reads/writes
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Configuration for connecting to a `YubiHSM2` over USB
+#[derive(Clone, Debug, Default)]
+pub struct UsbConfig {
+    /// Serial number of a specific device to connect to. When `None`, the
+    /// first matching device is used.
+    pub serial_number: Option<String>,
+
+    /// Timeout for USB reads/writes
+    pub timeout: Option<Duration>,
+}
+
+/// Connector which talks directly to a `YubiHSM2` over USB
+pub struct UsbConnector {
+    handle: Mutex<::libusb::DeviceHandle<'static>>,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    timeout: Duration,
+    serial_number: Option<String>,
+}
+
+// The underlying `libusb` handle is safe to share across threads behind a
+// mutex: all access is serialized through `send_command`/`status`.
+unsafe impl Send for UsbConnector {}
+unsafe impl Sync for UsbConnector {}
+
+impl UsbConnector {
+    /// Open a connection to a `YubiHSM2` attached via USB, optionally
+    /// selecting a specific device by serial number
+    pub fn open(config: UsbConfig) -> Result<Self, ConnectorError> {
+        // `libusb::Context` is leaked so the `'static` device handle borrow
+        // below remains valid for the lifetime of the connector.
+        let context: &'static Context =
+            Box::leak(Box::new(Context::new().context(ConnectionFailed)?));
+
+        let devices = context.devices().context(ConnectionFailed)?;
+
+        for device in devices.iter() {
+            let descriptor = match device.device_descriptor() {
+                Ok(d) => d,
+                Err(_) => continue,
+            };
+
+            if descriptor.vendor_id() != YUBICO_VENDOR_ID
+                || descriptor.product_id() != YUBIHSM2_PRODUCT_ID
+            {
+                continue;
+            }
+
+            let mut handle = device.open().context(ConnectionFailed)?;
+
+            // Read the device's serial number on a best-effort basis: it's
+            // needed to filter by `config.serial_number` below, and also
+            // reported back in `Status` so callers can tell which device
+            // they're talking to even when they didn't request one by
+            // serial number.
+            let serial_number = handle
+                .read_languages(Duration::from_secs(1))
+                .ok()
+                .and_then(|languages| languages.first().copied())
+                .and_then(|language| {
+                    handle
+                        .read_serial_number_string(language, &descriptor, Duration::from_secs(1))
+                        .ok()
+                });
+
+            if let Some(ref wanted_serial) = config.serial_number {
+                if serial_number.as_ref() != Some(wanted_serial) {
+                    continue;
+                }
+            }
+
+            let config_descriptor = device
+                .active_config_descriptor()
+                .context(ConnectionFailed)?;
+
+            let interface = config_descriptor
+                .interfaces()
+                .next()
+                .ok_or_else(|| ConnectorError::from(ConnectionFailed))?;
+
+            let interface_descriptor = interface
+                .descriptors()
+                .next()
+                .ok_or_else(|| ConnectorError::from(ConnectionFailed))?;
+
+            handle
+                .claim_interface(interface_descriptor.interface_number())
+                .context(ConnectionFailed)?;
+
+            let mut endpoint_in = None;
+            let mut endpoint_out = None;
+
+            for endpoint in interface_descriptor.endpoint_descriptors() {
+                if endpoint.transfer_type() != TransferType::Bulk {
+                    continue;
+                }
+
+                match endpoint.direction() {
+                    Direction::In => endpoint_in = Some(endpoint.address()),
+                    Direction::Out => endpoint_out = Some(endpoint.address()),
+                }
+            }
+
+            let endpoint_in = endpoint_in
+                .ok_or_else(|| ConnectorError::from(ConnectionFailed))?;
+            let endpoint_out = endpoint_out
+                .ok_or_else(|| ConnectorError::from(ConnectionFailed))?;
+
+            return Ok(Self {
+                handle: Mutex::new(handle),
+                endpoint_in,
+                endpoint_out,
+                timeout: config
+                    .timeout
+                    .unwrap_or_else(|| Duration::from_millis(DEFAULT_TIMEOUT_MS)),
+                serial_number,
+            });
+        }
+
+        connector_fail!(
+            DeviceNotFound,
+            "no YubiHSM2 found on USB{}",
+            config
+                .serial_number
+                .as_ref()
+                .map(|s| format!(" with serial number {}", s))
+                .unwrap_or_default()
+        );
+    }
+}
+
+impl Connector for UsbConnector {
+    fn status(&self) -> Result<Status, ConnectorError> {
+        // The USB transport has no separate status endpoint, so probe
+        // reachability directly: reading the device's supported languages
+        // is a real control-transfer round trip, so failing here means the
+        // device has gone away since it was opened.
+        let handle = self.handle.lock().unwrap();
+        handle
+            .read_languages(self.timeout)
+            .context(ConnectionFailed)?;
+
+        Ok(Status {
+            message: "OK".to_owned(),
+            serial_number: self.serial_number.clone(),
+        })
+    }
+
+    fn send_command(&self, _uuid: Uuid, command: Vec<u8>) -> Result<Vec<u8>, ConnectorError> {
+        let handle = self.handle.lock().unwrap();
+
+        handle
+            .write_bulk(self.endpoint_out, &command, self.timeout)
+            .context(RequestError)?;
+
+        let mut response = vec![0u8; 2048];
+        let bytes_read = handle
+            .read_bulk(self.endpoint_in, &mut response, self.timeout)
+            .context(ResponseError)?;
+
+        response.truncate(bytes_read);
+        Ok(response)
+    }
+}
@@ -0,0 +1,6 @@
+//! ECDSA commands
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/>
+
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
@@ -0,0 +1,102 @@
+//! Ethereum-style recoverable secp256k1 ECDSA signatures.
+//!
+//! Ethereum wallets (and tooling built on them, e.g. `ethers-rs`) expect a
+//! 65-byte `r || s || v` signature rather than the plain ASN.1 signature
+//! the HSM produces, so the signer's address can be recovered from the
+//! signature and message alone. This module derives that format from the
+//! HSM's DER signature plus its (cached) public key.
+
+use std::marker::PhantomData;
+
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1, Signature};
+
+use commands::{get_public_key, sign_ecdsa};
+use connector::Connector;
+use session::{Session, SessionError, SessionErrorKind::ProtocolError};
+use ObjectId;
+
+/// Signer which produces Ethereum-style recoverable secp256k1 signatures
+/// for a single asymmetric key, caching that key's public key so repeated
+/// signings don't need to re-query the device.
+pub struct RecoverableSigner<C: Connector> {
+    key_id: ObjectId,
+    public_key: PublicKey,
+    connector: PhantomData<C>,
+}
+
+impl<C: Connector> RecoverableSigner<C> {
+    /// Create a recoverable signer for `key_id`, fetching its public key
+    /// once up front
+    pub fn create(session: &mut Session<C>, key_id: ObjectId) -> Result<Self, SessionError> {
+        let public_key_bytes = get_public_key(session, key_id)?;
+
+        // The HSM returns the raw 64-byte (x, y) EC point with no SEC1 tag
+        // byte, whereas `PublicKey::from_slice` expects the standard
+        // uncompressed-point encoding (tag byte `0x04` followed by the
+        // point). Prepend it before parsing.
+        let mut uncompressed_point = Vec::with_capacity(1 + public_key_bytes.len());
+        uncompressed_point.push(0x04);
+        uncompressed_point.extend_from_slice(&public_key_bytes);
+
+        let public_key = PublicKey::from_slice(&uncompressed_point)
+            .map_err(|e| err!(ProtocolError, e))?;
+
+        Ok(Self {
+            key_id,
+            public_key,
+            connector: PhantomData,
+        })
+    }
+
+    /// The cached public key belonging to this signer's key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Sign a 32-byte keccak256 digest, returning a 65-byte `r || s || v`
+    /// recoverable signature
+    pub fn sign(
+        &self,
+        session: &mut Session<C>,
+        digest: &[u8; 32],
+    ) -> Result<[u8; 65], SessionError> {
+        let der_signature = sign_ecdsa(session, self.key_id, digest)?;
+
+        let mut signature =
+            Signature::from_der(&der_signature).map_err(|e| err!(ProtocolError, e))?;
+
+        // EIP-2: only the low-`s` form of a signature is considered valid
+        signature.normalize_s();
+
+        let message = Message::from_slice(digest).map_err(|e| err!(ProtocolError, e))?;
+        let compact = signature.serialize_compact();
+        let engine = Secp256k1::verification_only();
+
+        for recovery_id in 0..=1 {
+            let candidate = match RecoverableSignature::from_compact(
+                &compact,
+                RecoveryId::from_i32(recovery_id).unwrap(),
+            ) {
+                Ok(sig) => sig,
+                Err(_) => continue,
+            };
+
+            if engine
+                .recover(&message, &candidate)
+                .map(|recovered| recovered == self.public_key)
+                .unwrap_or(false)
+            {
+                let mut result = [0u8; 65];
+                result[..64].copy_from_slice(&compact);
+                result[64] = recovery_id as u8;
+                return Ok(result);
+            }
+        }
+
+        Err(err!(
+            ProtocolError,
+            "couldn't determine recovery id for secp256k1 signature"
+        ))
+    }
+}
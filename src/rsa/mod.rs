@@ -0,0 +1,6 @@
+//! RSA commands
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/>
+
+pub mod oaep;
+pub mod pkcs1;
@@ -0,0 +1,12 @@
+//! RSA-OAEP decryption
+//!
+//! Note: encryption itself is performed by the relying party using the
+//! public key; the `YubiHSM2` only performs the private-key decryption
+//! operation.
+
+mod algorithm;
+pub(crate) mod commands;
+mod data;
+
+pub use self::algorithm::Mgf1Hash;
+pub use self::data::DecryptedData;
@@ -0,0 +1,58 @@
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// MGF1 hash algorithm used when unpadding RSA-OAEP ciphertext
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Mgf1Hash {
+    /// MGF1 with SHA-1
+    Sha1,
+
+    /// MGF1 with SHA-256
+    Sha256,
+
+    /// MGF1 with SHA-384
+    Sha384,
+
+    /// MGF1 with SHA-512
+    Sha512,
+}
+
+impl Mgf1Hash {
+    /// Convert to the YubiHSM2 algorithm ID byte for this MGF1 hash
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Mgf1Hash::Sha1 => 32,
+            Mgf1Hash::Sha256 => 33,
+            Mgf1Hash::Sha384 => 34,
+            Mgf1Hash::Sha512 => 35,
+        }
+    }
+
+    /// Parse a YubiHSM2 algorithm ID byte into an `Mgf1Hash`
+    pub fn from_u8(byte: u8) -> Result<Self, String> {
+        Ok(match byte {
+            32 => Mgf1Hash::Sha1,
+            33 => Mgf1Hash::Sha256,
+            34 => Mgf1Hash::Sha384,
+            35 => Mgf1Hash::Sha512,
+            _ => return Err(format!("invalid MGF1 hash algorithm ID: {}", byte)),
+        })
+    }
+}
+
+// `Mgf1Hash` is sent on the wire as the YubiHSM2 algorithm ID byte, which
+// doesn't match the enum's declaration order, so it can't be derived the way
+// `AuditTag` is — serialize/deserialize through `to_u8`/`from_u8` instead,
+// the same way `CommandType` is hand-mapped to its protocol byte value.
+impl Serialize for Mgf1Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.to_u8())
+    }
+}
+
+impl<'de> Deserialize<'de> for Mgf1Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let byte = u8::deserialize(deserializer)?;
+        Mgf1Hash::from_u8(byte).map_err(de::Error::custom)
+    }
+}
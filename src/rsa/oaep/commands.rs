@@ -0,0 +1,50 @@
+//! RSA-OAEP decryption command
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Oaep.html>
+
+use super::{DecryptedData, Mgf1Hash};
+use commands::{Command, Response};
+use connector::Connector;
+use session::{Session, SessionError};
+use {CommandType, ObjectId};
+
+/// Decrypt ciphertext which was encrypted under an RSA-OAEP public key,
+/// recovering the original plaintext
+pub fn decrypt_oaep<C: Connector>(
+    session: &mut Session<C>,
+    key_id: ObjectId,
+    mgf1_hash_alg: Mgf1Hash,
+    ciphertext: Vec<u8>,
+    label_hash: Vec<u8>,
+) -> Result<DecryptedData, SessionError> {
+    session.send_encrypted_command(DecryptOaepCommand {
+        key_id,
+        mgf1_hash_alg,
+        ciphertext,
+        label_hash,
+    })
+}
+
+/// Request parameters for `rsa::oaep::commands::decrypt_oaep`
+#[derive(Serialize, Debug)]
+pub(crate) struct DecryptOaepCommand {
+    /// ID of the RSA decryption key
+    pub key_id: ObjectId,
+
+    /// MGF1 hash algorithm to use when unpadding the ciphertext
+    pub mgf1_hash_alg: Mgf1Hash,
+
+    /// Ciphertext to decrypt
+    pub ciphertext: Vec<u8>,
+
+    /// Hash of the OAEP label (typically the hash of an empty string)
+    pub label_hash: Vec<u8>,
+}
+
+impl Command for DecryptOaepCommand {
+    type ResponseType = DecryptedData;
+}
+
+impl Response for DecryptedData {
+    const COMMAND_TYPE: CommandType = CommandType::DecryptOaep;
+}
@@ -0,0 +1,16 @@
+/// Plaintext recovered by `rsa::oaep::commands::decrypt_oaep`
+#[derive(Clone, Debug, Deserialize)]
+pub struct DecryptedData(pub(crate) Vec<u8>);
+
+impl DecryptedData {
+    /// Borrow the decrypted plaintext
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<DecryptedData> for Vec<u8> {
+    fn from(decrypted: DecryptedData) -> Vec<u8> {
+        decrypted.0
+    }
+}
@@ -0,0 +1,88 @@
+use failure::{Context, Fail};
+use std::fmt::{self, Display};
+
+/// Session errors
+#[derive(Debug)]
+pub struct SessionError {
+    inner: Context<SessionErrorKind>,
+}
+
+/// Kinds of session errors
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Fail)]
+pub enum SessionErrorKind {
+    /// Couldn't create the session
+    #[fail(display = "couldn't create session")]
+    CreateFailed,
+
+    /// Authentication with the HSM failed
+    #[fail(display = "authentication failed")]
+    AuthFailed,
+
+    /// Protocol error occurred
+    #[fail(display = "protocol error")]
+    ProtocolError,
+
+    /// Error response from the HSM
+    #[fail(display = "HSM error")]
+    ResponseError,
+
+    /// The encrypted session was lost and needs to be reconnected
+    #[fail(display = "session lost")]
+    SessionLost,
+
+    /// Automatic reconnection of a lost session failed
+    #[fail(display = "session reconnection failed")]
+    ReconnectFailed,
+}
+
+impl SessionError {
+    /// Get the kind of error that occurred
+    pub fn kind(&self) -> SessionErrorKind {
+        *self.inner.get_context()
+    }
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl Fail for SessionError {
+    fn cause(&self) -> Option<&Fail> {
+        self.inner.cause()
+    }
+
+    fn backtrace(&self) -> Option<&::failure::Backtrace> {
+        self.inner.backtrace()
+    }
+}
+
+impl From<SessionErrorKind> for SessionError {
+    fn from(kind: SessionErrorKind) -> Self {
+        Context::new(kind).into()
+    }
+}
+
+impl From<Context<SessionErrorKind>> for SessionError {
+    fn from(inner: Context<SessionErrorKind>) -> Self {
+        SessionError { inner }
+    }
+}
+
+/// Create a new `SessionError` with the given kind and a formatted message,
+/// returning it as an `Err` from the current function
+macro_rules! session_fail {
+    ($kind:ident, $msg:expr) => {
+        return Err(::failure::Fail::context(
+            ::failure::Context::new($msg.to_string()),
+            ::session::error::SessionErrorKind::$kind,
+        ).into());
+    };
+    ($kind:ident, $fmt:expr, $($arg:tt)+) => {
+        return Err(::failure::Fail::context(
+            ::failure::Context::new(format!($fmt, $($arg)+)),
+            ::session::error::SessionErrorKind::$kind,
+        ).into());
+    };
+}
@@ -1,3 +1,5 @@
+use failure::ResultExt;
+use std::marker::PhantomData;
 use subtle::ConstantTimeEq;
 
 #[macro_use]
@@ -6,7 +8,9 @@ mod error;
 pub use self::error::{SessionError, SessionErrorKind};
 use super::{ObjectId, SessionId};
 use commands::{self, CloseSessionCommand, Command};
-use connector::{Connector, HttpConfig, HttpConnector, Status as ConnectorStatus};
+use connector::{
+    Connector, HttpConfig, HttpConnector, Status as ConnectorStatus, UsbConfig, UsbConnector,
+};
 use securechannel::{
     Challenge, Channel, CommandMessage, ResponseCode, ResponseMessage, StaticKeys,
 };
@@ -21,6 +25,10 @@ pub const PBKDF2_ITERATIONS: usize = 10_000;
 /// Status message returned from healthy connectors
 const CONNECTOR_STATUS_OK: &str = "OK";
 
+/// Default number of times to attempt reconnecting a lost session before
+/// giving up and returning the last `SessionErrorKind::SessionLost` error
+pub const DEFAULT_MAX_RECONNECT_ATTEMPTS: u8 = 3;
+
 /// Encrypted session with the `YubiHSM2`.
 /// A session is needed to perform any commands.
 ///
@@ -42,10 +50,15 @@ where
     /// Connector to send messages through
     connector: C,
 
+    /// ID of the auth key used to establish this session, cached so the
+    /// session can be re-established if the encrypted channel is lost
+    auth_key_id: ObjectId,
+
     /// Optional cached static keys for reconnecting lost sessions
-    // TODO: session reconnect support
-    #[allow(dead_code)]
     static_keys: Option<StaticKeys>,
+
+    /// Maximum number of times to attempt reconnecting a lost session
+    max_reconnect_attempts: u8,
 }
 
 // Special casing these for HttpConnector is a bit of a hack in that default
@@ -95,6 +108,47 @@ impl Session<HttpConnector> {
     }
 }
 
+impl Session<UsbConnector> {
+    /// Open a new session to a `YubiHSM2` attached via USB, authenticating
+    /// with the given keypair
+    pub fn create_usb(
+        connector_config: UsbConfig,
+        auth_key_id: ObjectId,
+        static_keys: StaticKeys,
+        reconnect: bool,
+    ) -> Result<Self, SessionError> {
+        let connector = UsbConnector::open(connector_config)?;
+        let status = connector.status()?;
+
+        if status.message != CONNECTOR_STATUS_OK {
+            session_fail!(
+                CreateFailed,
+                "bad status response from USB device: {}",
+                status.message
+            );
+        }
+
+        Self::new(connector, auth_key_id, static_keys, reconnect)
+    }
+
+    /// Open a new session to a `YubiHSM2` attached via USB, authenticating
+    /// with a given password
+    #[cfg(feature = "passwords")]
+    pub fn create_usb_from_password(
+        connector_config: UsbConfig,
+        auth_key_id: ObjectId,
+        password: &str,
+        reconnect: bool,
+    ) -> Result<Self, SessionError> {
+        Self::create_usb(
+            connector_config,
+            auth_key_id,
+            StaticKeys::derive_from_password(password.as_bytes(), PBKDF2_SALT, PBKDF2_ITERATIONS),
+            reconnect,
+        )
+    }
+}
+
 impl<C: Connector> Session<C> {
     /// Create a new encrypted session using the given connector, YubiHSM2 auth key ID, and
     /// static identity keys
@@ -130,7 +184,9 @@ impl<C: Connector> Session<C> {
             id: session_id,
             channel,
             connector,
+            auth_key_id,
             static_keys: static_keys_option,
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
         };
 
         session.authenticate()?;
@@ -148,6 +204,18 @@ impl<C: Connector> Session<C> {
         self.connector.status().map_err(|e| e.into())
     }
 
+    /// Configure the maximum number of times a lost session will be
+    /// automatically reconnected before giving up and returning the last
+    /// `SessionErrorKind::SessionLost` error. A reconnect attempt which
+    /// itself fails returns `SessionErrorKind::ReconnectFailed` right away.
+    ///
+    /// Has no effect unless `reconnect` was set to `true` when this session
+    /// was created.
+    #[inline]
+    pub fn set_max_reconnect_attempts(&mut self, max_reconnect_attempts: u8) {
+        self.max_reconnect_attempts = max_reconnect_attempts;
+    }
+
     /// Authenticate the current session with the `YubiHSM2`
     fn authenticate(&mut self) -> Result<(), SessionError> {
         let command = self.channel.authenticate_session()?;
@@ -163,7 +231,6 @@ impl<C: Connector> Session<C> {
         let cmd_type = cmd.command_type;
         let uuid = cmd.uuid;
 
-        // TODO: handle reconnecting when sessions are lost
         let response_bytes = self.connector.send_command(uuid, cmd.into())?;
         let response = ResponseMessage::parse(response_bytes)?;
 
@@ -183,19 +250,87 @@ impl<C: Connector> Session<C> {
         Ok(response)
     }
 
+    /// Send an already-encrypted command and parse its (still-encrypted)
+    /// response, the same as `send_command`, but additionally recognizing a
+    /// lost-session response at the transport level as
+    /// `SessionErrorKind::SessionLost`.
+    ///
+    /// This is split out from `send_command` because the latter is also
+    /// used for the session's very first, not-yet-authenticated handshake
+    /// (see `authenticate`), where a failure is never a "lost session" —
+    /// there's no session yet to lose.
+    fn send_command_checking_for_lost_session(
+        &mut self,
+        cmd: CommandMessage,
+    ) -> Result<ResponseMessage, SessionError> {
+        let cmd_type = cmd.command_type;
+        let uuid = cmd.uuid;
+
+        let response_bytes = self.connector.send_command(uuid, cmd.into())?;
+        let response = ResponseMessage::parse(response_bytes)?;
+
+        if response.is_err() {
+            if Self::is_lost_session_response(response.code) {
+                session_fail!(SessionLost, "HSM session lost: {:?}", response.code);
+            }
+
+            session_fail!(ResponseError, "HSM error: {:?}", response.code);
+        }
+
+        if response.command().unwrap() != cmd_type {
+            session_fail!(
+                ProtocolError,
+                "command type mismatch: expected {:?}, got {:?}",
+                cmd_type,
+                response.command().unwrap()
+            );
+        }
+
+        Ok(response)
+    }
+
     /// Encrypt a command and send it to the card, then authenticate and
-    /// decrypt the response
+    /// decrypt the response.
+    ///
+    /// If the encrypted channel has been dropped (either the connector
+    /// reports a dropped session, or the HSM responds with a
+    /// session-expired/authentication error) and this session was created
+    /// with `reconnect: true`, transparently re-establish the channel and
+    /// retry the command, up to `max_reconnect_attempts` times.
     pub(crate) fn send_encrypted_command<T: Command>(
         &mut self,
         command: T,
     ) -> Result<T::ResponseType, SessionError> {
-        let plaintext_cmd = command.into();
+        let plaintext_cmd: CommandMessage = command.into();
+        let reconnect_enabled = self.static_keys.is_some();
+        let max_reconnect_attempts = self.max_reconnect_attempts;
+
+        let mut attempt = SendEncryptedCommand {
+            session: self,
+            plaintext_cmd,
+            command_type: PhantomData::<T>,
+        };
+
+        retry_on_lost_session(reconnect_enabled, max_reconnect_attempts, &mut attempt)
+    }
+
+    /// Make a single attempt at encrypting, sending, and decrypting a
+    /// command, without any reconnect handling
+    fn try_send_encrypted_command<T: Command>(
+        &mut self,
+        plaintext_cmd: CommandMessage,
+    ) -> Result<T::ResponseType, SessionError> {
         let encrypted_cmd = self.channel.encrypt_command(plaintext_cmd)?;
 
-        let encrypted_response = self.send_command(encrypted_cmd)?;
+        let encrypted_response =
+            self.send_command_checking_for_lost_session(encrypted_cmd)?;
         let response = self.channel.decrypt_response(encrypted_response)?;
 
         if response.is_err() {
+            if Self::is_lost_session_response(response.code) {
+                session_fail!(SessionLost, "HSM session lost: {:?}", response.code);
+            }
+
             // TODO: factor this into ResponseMessage or ResponseCode?
             let description = match response.code {
                 ResponseCode::MemoryError => {
@@ -218,6 +353,135 @@ impl<C: Connector> Session<C> {
 
         deserialize(response.data.as_ref()).map_err(|e| e.into())
     }
+
+    /// Tear down the current (dead) encrypted channel and re-establish a
+    /// fresh one using the cached static keys, then re-authenticate.
+    fn reconnect(&mut self) -> Result<(), SessionError> {
+        if self.static_keys.is_none() {
+            session_fail!(
+                ReconnectFailed,
+                "session reconnection was not enabled for this session"
+            );
+        }
+
+        let host_challenge = Challenge::random();
+
+        let (session_id, session_response) =
+            commands::create_session(&self.connector, self.auth_key_id, host_challenge)
+                .context(SessionErrorKind::ReconnectFailed)?;
+
+        let channel = Channel::new(
+            session_id,
+            self.static_keys.as_ref().unwrap(),
+            host_challenge,
+            session_response.card_challenge,
+        );
+
+        if channel
+            .card_cryptogram()
+            .ct_eq(&session_response.card_cryptogram)
+            .unwrap_u8()
+            != 1
+        {
+            session_fail!(ReconnectFailed, "card cryptogram mismatch on reconnect!");
+        }
+
+        self.id = session_id;
+        self.channel = channel;
+
+        self.authenticate().context(SessionErrorKind::ReconnectFailed)?;
+
+        Ok(())
+    }
+
+    /// Does this HSM response code indicate the encrypted session is no
+    /// longer usable and should be re-established?
+    fn is_lost_session_response(code: ResponseCode) -> bool {
+        match code {
+            ResponseCode::AuthenticationFailed
+            | ResponseCode::InvalidSession
+            | ResponseCode::SessionFailed => true,
+            _ => false,
+        }
+    }
+}
+
+/// Does this error indicate the encrypted channel was lost and should be
+/// re-established? Set by `send_command_checking_for_lost_session` whenever
+/// the transport-level response (which covers both a connector-reported
+/// dropped session and an HSM session-expired/authentication response code)
+/// indicates as much, and by `try_send_encrypted_command` when the decrypted
+/// response carries such a code.
+fn is_lost_session_error(error: &SessionError) -> bool {
+    error.kind() == SessionErrorKind::SessionLost
+}
+
+/// One attempt at an operation which may need to reconnect and retry, as
+/// performed by `retry_on_lost_session`. Factored out as a trait (rather
+/// than passing `send_encrypted_command` two closures that would each need
+/// their own `&mut Session`) so the retry/give-up policy below can be
+/// exercised in tests without a real encrypted channel.
+trait RetryableAttempt {
+    /// Type returned by a successful attempt
+    type Output;
+
+    /// Make one attempt at the operation
+    fn attempt(&mut self) -> Result<Self::Output, SessionError>;
+
+    /// Re-establish whatever was lost, so a further attempt can succeed
+    fn reconnect(&mut self) -> Result<(), SessionError>;
+}
+
+/// Run `attempt.attempt()`, and if it fails with a lost-session error,
+/// `attempt.reconnect()` and retry, up to `max_reconnect_attempts` times.
+/// Returns the first successful result, or the most recent error once
+/// reconnecting is disabled, the error isn't session-loss, or the attempt
+/// limit is reached.
+fn retry_on_lost_session<A: RetryableAttempt>(
+    reconnect_enabled: bool,
+    max_reconnect_attempts: u8,
+    attempt: &mut A,
+) -> Result<A::Output, SessionError> {
+    let mut reconnect_attempts = 0;
+
+    loop {
+        match attempt.attempt() {
+            Ok(response) => return Ok(response),
+            Err(error) => {
+                if reconnect_enabled
+                    && is_lost_session_error(&error)
+                    && reconnect_attempts < max_reconnect_attempts
+                {
+                    reconnect_attempts += 1;
+                    attempt.reconnect()?;
+                    continue;
+                }
+
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// `RetryableAttempt` which sends a single encrypted command through a
+/// `Session`, reconnecting that same session on a lost-session error
+struct SendEncryptedCommand<'s, C: Connector, T: Command> {
+    session: &'s mut Session<C>,
+    plaintext_cmd: CommandMessage,
+    command_type: PhantomData<T>,
+}
+
+impl<'s, C: Connector, T: Command> RetryableAttempt for SendEncryptedCommand<'s, C, T> {
+    type Output = T::ResponseType;
+
+    fn attempt(&mut self) -> Result<T::ResponseType, SessionError> {
+        self.session
+            .try_send_encrypted_command::<T>(self.plaintext_cmd.clone())
+    }
+
+    fn reconnect(&mut self) -> Result<(), SessionError> {
+        self.session.reconnect()
+    }
 }
 
 /// Close session automatically on drop
@@ -227,3 +491,75 @@ impl<C: Connector> Drop for Session<C> {
         debug_assert_eq!(err.map(|e| e.kind()), None);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake `RetryableAttempt` which fails with `SessionLost` a fixed number
+    /// of times before succeeding, and counts how many times it was asked
+    /// to reconnect
+    struct FlakyAttempt {
+        fails_remaining: u8,
+        reconnect_calls: u8,
+    }
+
+    impl RetryableAttempt for FlakyAttempt {
+        type Output = &'static str;
+
+        fn attempt(&mut self) -> Result<&'static str, SessionError> {
+            if self.fails_remaining > 0 {
+                self.fails_remaining -= 1;
+                Err(SessionErrorKind::SessionLost.into())
+            } else {
+                Ok("ok")
+            }
+        }
+
+        fn reconnect(&mut self) -> Result<(), SessionError> {
+            self.reconnect_calls += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn retry_on_lost_session_retries_after_a_successful_reconnect() {
+        let mut attempt = FlakyAttempt {
+            fails_remaining: 1,
+            reconnect_calls: 0,
+        };
+
+        let result = retry_on_lost_session(true, 3, &mut attempt);
+
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(attempt.reconnect_calls, 1);
+    }
+
+    #[test]
+    fn retry_on_lost_session_gives_up_after_max_reconnect_attempts() {
+        let mut attempt = FlakyAttempt {
+            fails_remaining: 10,
+            reconnect_calls: 0,
+        };
+
+        let error = retry_on_lost_session(true, 2, &mut attempt).unwrap_err();
+
+        // Gives up instead of looping forever...
+        assert_eq!(attempt.reconnect_calls, 2);
+        // ...and the real cause is still surfaced, not masked.
+        assert_eq!(error.kind(), SessionErrorKind::SessionLost);
+    }
+
+    #[test]
+    fn retry_on_lost_session_does_not_reconnect_when_disabled() {
+        let mut attempt = FlakyAttempt {
+            fails_remaining: 10,
+            reconnect_calls: 0,
+        };
+
+        let error = retry_on_lost_session(false, 3, &mut attempt).unwrap_err();
+
+        assert_eq!(attempt.reconnect_calls, 0);
+        assert_eq!(error.kind(), SessionErrorKind::SessionLost);
+    }
+}